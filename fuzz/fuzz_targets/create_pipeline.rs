@@ -0,0 +1,104 @@
+//! Builds the demo's pipelines from arbitrary input, the path shaders pushed
+//! over the control socket or the WebSocket server end up on. Invalid
+//! modules must be rejected by `Renderer::set_shader`, any panic escaping
+//! it is a crash.
+//!
+//! Needs an adapter, input is ignored without one. Run with
+//! `cargo fuzz run create_pipeline fuzz/corpus/create_pipeline corpus`,
+//! the top level `corpus` directory seeds it with the demo's shaders.
+#![no_main]
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use std::{
+    cell::{Cell, RefCell},
+    panic,
+};
+
+use rsh_wgpu::{
+    renderer::Renderer,
+    shader::{self, Stage},
+};
+
+struct State {
+    _instance: wgpu::Instance,
+    device: wgpu::Device,
+    renderer: Renderer,
+}
+
+thread_local! {
+    /// Whether a panic is expected to be caught by `set_shader`
+    static IN_SET_SHADER: Cell<bool> = Cell::new(false);
+    /// Created on the first input, `None` if there's no adapter
+    static STATE: RefCell<Option<State>> = RefCell::new(init());
+}
+
+fn init() -> Option<State> {
+    // Validation errors are reported by panicking and caught in
+    // `set_shader`, only panics escaping it are printed
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if !IN_SET_SHADER.with(Cell::get) {
+            default_hook(info);
+        }
+    }));
+
+    let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+    let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::Default,
+        compatible_surface: None,
+    }))?;
+    let (device, _queue) = block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default(),
+            shader_validation: true,
+        },
+        None,
+    ))
+    .ok()?;
+
+    let vs_spirv = shader::compile(include_str!("../../corpus/triangle.vert.rsh")).ok()?;
+    let fs_spirv = shader::compile(include_str!("../../corpus/triangle.frag.rsh")).ok()?;
+    let renderer = Renderer::new(
+        &device,
+        wgpu::TextureFormat::Bgra8UnormSrgb,
+        1,
+        1,
+        &vs_spirv,
+        &fs_spirv,
+    );
+
+    Some(State {
+        _instance: instance,
+        device,
+        renderer,
+    })
+}
+
+fn set_shader(state: &mut State, spirv: &[u32]) {
+    IN_SET_SHADER.with(|flag| flag.set(true));
+    for &stage in &[Stage::Vertex, Stage::Fragment] {
+        let _ = state.renderer.set_shader(&state.device, stage, spirv);
+    }
+    IN_SET_SHADER.with(|flag| flag.set(false));
+}
+
+fuzz_target!(|data: &[u8]| {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = match state.as_mut() {
+            Some(state) => state,
+            None => return,
+        };
+
+        if let Ok(spirv) = shader::parse_spv(data) {
+            set_shader(state, &spirv);
+        }
+
+        if let Ok(source) = std::str::from_utf8(data) {
+            if let Ok(spirv) = shader::compile(source) {
+                set_shader(state, &spirv);
+            }
+        }
+    });
+});
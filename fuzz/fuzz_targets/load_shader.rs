@@ -0,0 +1,18 @@
+//! Feeds arbitrary input through the same path shaders take when loaded
+//! from files, the control socket or the WebSocket server. Malformed input
+//! must produce an error, never a panic.
+//!
+//! Run with `cargo fuzz run load_shader fuzz/corpus/load_shader corpus`, the
+//! top level `corpus` directory seeds it with the demo's shaders.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use rsh_wgpu::shader;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = shader::parse_spv(data);
+
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = shader::compile(source);
+    }
+});
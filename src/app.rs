@@ -0,0 +1,424 @@
+#[cfg(feature = "metrics")]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use winit::{dpi::PhysicalSize, event::*, event_loop::ControlFlow, window::Window};
+
+use crate::{
+    capture::{self, Frame},
+    control::Command,
+    input::{InputEvent, Recorder, Replay},
+    options::Options,
+    pipe::FramePipe,
+    renderer::{ClearColor, Renderer, Viewport},
+    shader,
+    stats::{FrameStats, StatsLog},
+};
+
+/// Seconds the replay's timeline moves with the arrow keys
+const SCRUB_STEP: f32 = 1.0;
+
+/// State driven by the event loop
+pub struct App {
+    pub window: Window,
+    surface: wgpu::Surface,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    sc_desc: wgpu::SwapChainDescriptor,
+    swap_chain: wgpu::SwapChain,
+    renderer: Renderer,
+    /// Set by the options or a control command, `None` keeps the renderer's
+    /// default
+    clear_color: Option<ClearColor>,
+    shader_cache: Option<shader::Cache>,
+    options: Options,
+    start: Instant,
+    /// Added to the clock to move it to where the replay was scrubbed to
+    time_offset: f32,
+    /// Time the clock was stopped at while the replay is paused
+    paused_at: Option<f32>,
+    frame_index: u64,
+    /// When the previous frame started rendering
+    last_frame: Option<Instant>,
+    stats_log: Option<StatsLog>,
+    pipe: Option<FramePipe>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<crate::metrics::Metrics>>,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
+}
+
+impl App {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        window: Window,
+        surface: wgpu::Surface,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        sc_desc: wgpu::SwapChainDescriptor,
+        renderer: Renderer,
+        shader_cache: Option<shader::Cache>,
+        options: Options,
+    ) -> Self {
+        let swap_chain = device.create_swap_chain(&surface, &sc_desc);
+        let recorder = options.record.as_ref().map(|path| {
+            let mut recorder = Recorder::create(path).unwrap();
+            let size = InputEvent::Resized {
+                width: sc_desc.width,
+                height: sc_desc.height,
+            };
+
+            // Replays start from the recorded window size, which also makes
+            // rewinding restore it
+            recorder.record(0.0, &size).unwrap();
+            recorder
+        });
+        let stats_log = options
+            .stats_log
+            .as_ref()
+            .map(|path| StatsLog::create(path).unwrap());
+        let pipe = options.pipe_frames.as_ref().map(|path| {
+            FramePipe::create(path, sc_desc.width, sc_desc.height, options.pipe_fps).unwrap()
+        });
+        #[cfg(feature = "metrics")]
+        let metrics = options.metrics.as_ref().map(|addr| {
+            let metrics = Arc::new(crate::metrics::Metrics::default());
+            crate::metrics::spawn(addr, metrics.clone()).unwrap();
+            metrics
+        });
+        let replay = options
+            .replay
+            .as_ref()
+            .map(|path| Replay::load(path).unwrap());
+
+        App {
+            window,
+            surface,
+            device,
+            queue,
+            sc_desc,
+            swap_chain,
+            renderer,
+            clear_color: options.clear_color,
+            shader_cache,
+            options,
+            start: Instant::now(),
+            time_offset: 0.0,
+            paused_at: None,
+            frame_index: 0,
+            last_frame: None,
+            stats_log,
+            pipe,
+            #[cfg(feature = "metrics")]
+            metrics,
+            recorder,
+            replay,
+        }
+    }
+
+    /// Time in seconds used by animations and input recordings
+    fn time(&self) -> f32 {
+        if let Some(time) = self.paused_at {
+            return time;
+        }
+
+        self.clock() + self.time_offset
+    }
+
+    /// Seconds since startup, unaffected by pausing and seeking
+    fn clock(&self) -> f32 {
+        // Hashed frames advance by a fixed step so every run renders the same frames
+        match self.options.hash_frames {
+            Some(_) => self.frame_index as f32 / 60.0,
+            None => self.start.elapsed().as_secs_f32(),
+        }
+    }
+
+    /// Handles input coming from the window
+    pub fn window_input(&mut self, input: InputEvent, control_flow: &mut ControlFlow) {
+        let time = self.time();
+
+        if let Some(ref mut recorder) = self.recorder {
+            if let Err(e) = recorder.record(time, &input) {
+                eprintln!("Failed to record input: {}", e);
+            }
+        }
+
+        // While replaying live input only controls the replay's timeline,
+        // apart from the window's own resizes which the replay triggers
+        if self.replay.is_some() {
+            if let Some(key) = input.pressed_key() {
+                return self.scrub(key, control_flow);
+            } else if !matches!(input, InputEvent::Resized { .. }) {
+                return;
+            }
+        }
+
+        self.input(input, control_flow);
+    }
+
+    /// Moves through the replay's timeline with the arrow keys, home and
+    /// space, the recording is played back at its original speed otherwise
+    fn scrub(&mut self, key: VirtualKeyCode, control_flow: &mut ControlFlow) {
+        let time = self.time();
+
+        match key {
+            VirtualKeyCode::Left => self.seek(time - SCRUB_STEP, control_flow),
+            VirtualKeyCode::Right => self.seek(time + SCRUB_STEP, control_flow),
+            VirtualKeyCode::Home => self.seek(0.0, control_flow),
+            VirtualKeyCode::Space => match self.paused_at.take() {
+                Some(paused_at) => self.time_offset = paused_at - self.clock(),
+                None => self.paused_at = Some(time),
+            },
+            // Still allow leaving in the middle of a replay
+            VirtualKeyCode::Escape => *control_flow = ControlFlow::Exit,
+            _ => {}
+        }
+    }
+
+    /// Moves the replay to `target` seconds by fast-forwarding its input
+    fn seek(&mut self, target: f32, control_flow: &mut ControlFlow) {
+        let target = target.max(0.0);
+        let time = self.time();
+
+        match self.paused_at {
+            Some(ref mut paused_at) => *paused_at = target,
+            None => self.time_offset += target - time,
+        }
+
+        let due = match self.replay {
+            Some(ref mut replay) => {
+                if target < time {
+                    // Input can't be undone, so start over from the initial
+                    // state and replay everything up to the target instead
+                    replay.rewind();
+                }
+
+                eprintln!("Replay at {:.2}s of {:.2}s", target, replay.duration());
+                replay.due(target)
+            }
+            None => return,
+        };
+
+        if target < time {
+            self.renderer.outline = self.options.outline;
+        }
+
+        for input in due {
+            self.replay_input(input, true, control_flow);
+        }
+
+        self.window.request_redraw();
+    }
+
+    /// Handles input from the replay, `seeking` skips input with side
+    /// effects outside of the rendered frame
+    fn replay_input(&mut self, input: InputEvent, seeking: bool, control_flow: &mut ControlFlow) {
+        match input {
+            // Resize the window itself so the swap chain keeps matching it
+            InputEvent::Resized { width, height } => {
+                self.window.set_inner_size(PhysicalSize::new(width, height))
+            }
+            _ if seeking
+                && matches!(
+                    input.pressed_key(),
+                    Some(VirtualKeyCode::C) | Some(VirtualKeyCode::Escape)
+                ) => {}
+            input => self.input(input, control_flow),
+        }
+    }
+
+    fn input(&mut self, input: InputEvent, control_flow: &mut ControlFlow) {
+        match input {
+            InputEvent::Keyboard { input } => match input {
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::Escape),
+                    ..
+                } => *control_flow = ControlFlow::Exit,
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::C),
+                    ..
+                } => {
+                    if let Err(e) = capture::copy_to_clipboard(&self.capture()) {
+                        eprintln!("Failed to copy frame to the clipboard: {}", e);
+                    }
+                }
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::O),
+                    ..
+                } => {
+                    self.renderer.outline = !self.renderer.outline;
+                    self.window.request_redraw();
+                }
+                _ => {}
+            },
+            InputEvent::Resized { width, height } => {
+                self.sc_desc.width = width;
+                self.sc_desc.height = height;
+                self.swap_chain = self.device.create_swap_chain(&self.surface, &self.sc_desc);
+                self.renderer.resize(&self.device, width, height);
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs once all pending events were handled
+    pub fn update(&mut self, control_flow: &mut ControlFlow) {
+        let time = self.time();
+        let due = match self.replay {
+            Some(ref mut replay) => replay.due(time),
+            None => Vec::new(),
+        };
+
+        for input in due {
+            self.replay_input(input, false, control_flow);
+        }
+
+        if self.options.letterbox
+            || self.options.hash_frames.is_some()
+            || self.replay.is_some()
+            || self.pipe.is_some()
+            || matches!(self.clear_color, Some(ClearColor::Cycle))
+        {
+            self.window.request_redraw();
+        }
+    }
+
+    pub fn redraw(&mut self, control_flow: &mut ControlFlow) {
+        if self.options.letterbox {
+            // Sweep the aspect ratio between 1:1 and 2.39:1
+            let aspect = 1.695 + 0.695 * self.time().sin();
+            let viewport = Viewport::letterbox(self.sc_desc.width, self.sc_desc.height, aspect);
+
+            self.renderer.set_viewport(Some(viewport));
+            self.renderer.set_scissor_rect(Some(viewport.into()));
+        }
+
+        if let Some(clear_color) = self.clear_color {
+            self.renderer.clear_color = clear_color.at(self.time());
+        }
+
+        let frame = self
+            .swap_chain
+            .get_current_frame()
+            .expect("Timeout getting texture")
+            .output;
+
+        let frame_start = Instant::now();
+        let frame_time = self
+            .last_frame
+            .replace(frame_start)
+            .map(|last| frame_start - last);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(std::borrow::Cow::Borrowed("Render Encoder")),
+            });
+
+        self.renderer.draw(&mut encoder, &frame.view);
+
+        self.queue.submit(Some(encoder.finish()));
+
+        let stats = FrameStats {
+            frame: self.frame_index,
+            time: self.time(),
+            frame_time_ms: frame_time.map(millis),
+            cpu_time_ms: millis(frame_start.elapsed()),
+            draw_calls: self.renderer.draw_calls(),
+        };
+
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(ref metrics) = self.metrics {
+                metrics.record_frame(frame_time);
+            }
+        }
+
+        if let Some(ref mut stats_log) = self.stats_log {
+            if let Err(e) = stats_log.write(&stats) {
+                eprintln!("Failed to write frame stats: {}", e);
+            }
+        }
+
+        // Paced by the clock rather than the scrubbable time, so seeking or
+        // pausing doesn't stall or flood the stream
+        let clock = self.clock();
+        let due = self.pipe.as_ref().map_or(0, |pipe| pipe.due(clock));
+        if due > 0 {
+            let frame = self.capture();
+            // Frames missed while rendering slower than the stream's rate
+            // repeat the latest one so the stream keeps its timing
+            let result = match self.pipe {
+                Some(ref mut pipe) => (0..due).try_for_each(|_| pipe.write(&frame)),
+                None => Ok(()),
+            };
+
+            if let Err(e) = result {
+                eprintln!("Stopped piping frames: {}", e);
+                self.pipe = None;
+            }
+        }
+
+        if let Some(count) = self.options.hash_frames {
+            let frame = self.capture();
+
+            println!(
+                "{} {}x{} {:016x}",
+                self.frame_index,
+                frame.width,
+                frame.height,
+                frame.hash()
+            );
+
+            if self.frame_index + 1 >= count {
+                *control_flow = ControlFlow::Exit;
+            }
+        }
+
+        self.frame_index += 1;
+    }
+
+    pub fn command(&mut self, command: Command) -> Result<(), String> {
+        let result = match command {
+            Command::SetClearColor { color } => ClearColor::parse(&color).map(|color| {
+                self.clear_color = Some(color);
+                self.renderer.clear_color = color.at(self.time());
+            }),
+            Command::LoadShader { stage, path } => shader::load(&path, self.shader_cache.as_ref())
+                .map_err(|e| e.to_string())
+                .and_then(|spirv| self.renderer.set_shader(&self.device, stage, &spirv)),
+            Command::SetShaderSource { stage, source } => {
+                shader::compile_cached(&source, self.shader_cache.as_ref())
+                    .map_err(|e| e.to_string())
+                    .and_then(|spirv| self.renderer.set_shader(&self.device, stage, &spirv))
+            }
+            Command::CaptureFrame { path } => {
+                capture::save_png(&self.capture(), &path).map_err(|e| e.to_string())
+            }
+        };
+
+        self.window.request_redraw();
+        result
+    }
+
+    /// Renders the current frame again and reads it back
+    fn capture(&self) -> Frame {
+        let renderer = &self.renderer;
+
+        capture::capture(
+            &self.device,
+            &self.queue,
+            self.options.format,
+            self.sc_desc.width,
+            self.sc_desc.height,
+            |encoder, view| renderer.draw(encoder, view),
+        )
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
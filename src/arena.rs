@@ -0,0 +1,73 @@
+use std::{mem, ops::Range};
+use wgpu::util::DeviceExt;
+
+/// Collects the vertices of many small meshes so they share a single
+/// buffer, which is bound once and drawn from by vertex ranges
+#[derive(Debug, Default)]
+pub struct ArenaBuilder {
+    data: Vec<u8>,
+}
+
+impl ArenaBuilder {
+    /// Appends `vertices`, returning the range they occupy when the arena is
+    /// bound with the stride of `V`
+    pub fn push_vertices<V: bytemuck::Pod>(&mut self, vertices: &[V]) -> Range<u32> {
+        // Pad to a whole vertex so the range can be expressed in vertices
+        let stride = mem::size_of::<V>();
+        let padding = (stride - self.data.len() % stride) % stride;
+        self.data.resize(self.data.len() + padding, 0);
+
+        let start = (self.data.len() / stride) as u32;
+        self.data.extend_from_slice(bytemuck::cast_slice(vertices));
+
+        start..start + vertices.len() as u32
+    }
+
+    /// Uploads everything pushed so far in one buffer
+    pub fn build(self, device: &wgpu::Device, label: &str) -> Arena {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: &self.data,
+            usage: wgpu::BufferUsage::VERTEX,
+        });
+
+        Arena { buffer }
+    }
+}
+
+/// A buffer holding the vertices of many meshes, each drawn through the
+/// range [`ArenaBuilder::push_vertices`] returned for it
+pub struct Arena {
+    buffer: wgpu::Buffer,
+}
+
+impl Arena {
+    pub fn slice(&self) -> wgpu::BufferSlice {
+        self.buffer.slice(..)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_are_in_vertices() {
+        let mut arena = ArenaBuilder::default();
+
+        assert_eq!(arena.push_vertices(&[[0f32; 4]; 3]), 0..3);
+        assert_eq!(arena.push_vertices(&[[1f32; 4]; 2]), 3..5);
+        assert_eq!(arena.data.len(), 5 * 16);
+    }
+
+    #[test]
+    fn pads_to_whole_vertices() {
+        let mut arena = ArenaBuilder::default();
+
+        assert_eq!(arena.push_vertices(&[1u8, 2, 3]), 0..3);
+        assert_eq!(arena.push_vertices(&[[1f32; 4]]), 1..2);
+        assert_eq!(arena.data[..3], [1, 2, 3]);
+        assert!(arena.data[3..16].iter().all(|&byte| byte == 0));
+        assert_eq!(arena.data.len(), 32);
+    }
+}
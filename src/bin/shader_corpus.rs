@@ -0,0 +1,229 @@
+//! Compiles a directory of rsh shaders and builds the demo's pipelines from
+//! them on every available backend, printing a pass/fail matrix.
+//!
+//! Shaders come in pairs named `<name>.vert.rsh` and `<name>.frag.rsh`, the
+//! directory defaults to `corpus`. The process exits with an error if any
+//! pair fails to compile or to build on a backend.
+//!
+//! Run with `cargo run --bin shader_corpus -- [dir]`
+use futures::executor::block_on;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    process,
+};
+
+use rsh_wgpu::{
+    panic_message,
+    renderer::Renderer,
+    shader::{self, Stage},
+};
+
+/// A shader pair's sources, either stage may be missing from the corpus
+#[derive(Default)]
+struct Pair {
+    vert: Option<PathBuf>,
+    frag: Option<PathBuf>,
+}
+
+enum Outcome {
+    Pass,
+    Fail(String),
+}
+
+impl Outcome {
+    fn cell(&self) -> &'static str {
+        match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail(_) => "FAIL",
+        }
+    }
+}
+
+fn main() {
+    let dir = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("corpus"), PathBuf::from);
+
+    let pairs = match read_corpus(&dir) {
+        Ok(pairs) => pairs,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", dir.display(), e);
+            process::exit(1);
+        }
+    };
+
+    // Compilation and pipeline creation can report errors by panicking,
+    // those are collected into the matrix instead of being printed as they
+    // happen
+    panic::set_hook(Box::new(|_| {}));
+
+    let instance = wgpu::Instance::new(wgpu::BackendBit::all());
+    let mut adapters: Vec<wgpu::Adapter> = Vec::new();
+    for adapter in instance.enumerate_adapters(wgpu::BackendBit::all()) {
+        let backend = adapter.get_info().backend;
+
+        // One adapter per backend is enough to exercise its shader compiler
+        if adapters
+            .iter()
+            .all(|other| other.get_info().backend != backend)
+        {
+            adapters.push(adapter);
+        }
+    }
+
+    let mut header = vec![String::from("shader"), String::from("compile")];
+    header.extend(
+        adapters
+            .iter()
+            .map(|adapter| format!("{:?}", adapter.get_info().backend)),
+    );
+
+    let mut rows = Vec::new();
+    let mut failures = Vec::new();
+
+    for (name, pair) in &pairs {
+        let mut outcomes = Vec::new();
+
+        // A panic in the compiler is a regression like any other failure
+        let compiled = panic::catch_unwind(AssertUnwindSafe(|| compile_pair(pair)))
+            .unwrap_or_else(|payload| Err(panic_message(&*payload)));
+
+        match compiled {
+            Ok((vs_spirv, fs_spirv)) => {
+                outcomes.push((String::from("compile"), Outcome::Pass));
+
+                for adapter in &adapters {
+                    let backend = format!("{:?}", adapter.get_info().backend);
+                    outcomes.push((backend, build(adapter, &vs_spirv, &fs_spirv)));
+                }
+            }
+            Err(e) => outcomes.push((String::from("compile"), Outcome::Fail(e))),
+        }
+
+        let mut row = vec![name.clone()];
+        row.extend(
+            outcomes
+                .iter()
+                .map(|(_, outcome)| outcome.cell().to_string()),
+        );
+        // Pairs that don't compile can't be built on any backend
+        row.resize(header.len(), String::from("-"));
+        rows.push(row);
+
+        for (column, outcome) in outcomes {
+            if let Outcome::Fail(e) = outcome {
+                failures.push(format!("{} ({}): {}", name, column, e));
+            }
+        }
+    }
+
+    print_table(&header, &rows);
+
+    if !failures.is_empty() {
+        println!();
+        for failure in &failures {
+            println!("{}", failure);
+        }
+
+        process::exit(1);
+    }
+}
+
+/// Groups the `.vert.rsh` and `.frag.rsh` files in `dir` by name
+fn read_corpus(dir: &Path) -> io::Result<BTreeMap<String, Pair>> {
+    let mut pairs = BTreeMap::<String, Pair>::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+
+        for &stage in &[Stage::Vertex, Stage::Fragment] {
+            let suffix = format!(".{}.rsh", stage.tag());
+
+            if let Some(name) = file_name.strip_suffix(suffix.as_str()) {
+                let pair = pairs.entry(name.to_string()).or_default();
+
+                match stage {
+                    Stage::Vertex => pair.vert = Some(path.clone()),
+                    Stage::Fragment => pair.frag = Some(path.clone()),
+                }
+            }
+        }
+    }
+
+    Ok(pairs)
+}
+
+fn compile_pair(pair: &Pair) -> Result<(Vec<u32>, Vec<u32>), String> {
+    let compile = |path: &Option<PathBuf>, stage: Stage| match path {
+        Some(path) => shader::load(path, None).map_err(|e| e.to_string()),
+        None => Err(format!("Missing the {} stage", stage.tag())),
+    };
+
+    Ok((
+        compile(&pair.vert, Stage::Vertex)?,
+        compile(&pair.frag, Stage::Fragment)?,
+    ))
+}
+
+/// Builds the renderer's pipelines on `adapter`, every build gets its own
+/// device so a failure can't leave state behind for the next pair
+fn build(adapter: &wgpu::Adapter, vs_spirv: &[u32], fs_spirv: &[u32]) -> Outcome {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let (device, _queue) = block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+                shader_validation: true,
+            },
+            None,
+        ))
+        .expect("Failed to create a device");
+
+        Renderer::new(
+            &device,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+            1,
+            1,
+            vs_spirv,
+            fs_spirv,
+        );
+        device.poll(wgpu::Maintain::Wait);
+    }));
+
+    match result {
+        Ok(()) => Outcome::Pass,
+        Err(payload) => Outcome::Fail(panic_message(&*payload)),
+    }
+}
+
+fn print_table(header: &[String], rows: &[Vec<String>]) {
+    let widths: Vec<_> = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].len())
+                .chain(Some(header[column].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in Some(header)
+        .into_iter()
+        .chain(rows.iter().map(Vec::as_slice))
+    {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+            .collect();
+
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
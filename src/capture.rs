@@ -0,0 +1,211 @@
+use futures::executor::block_on;
+use std::{borrow::Cow, fs::File, io::BufWriter, path::Path};
+
+use crate::renderer::OutputFormat;
+
+/// A frame read back from the GPU as tightly packed RGBA8 rows
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    /// Hash of the frame's size and pixels
+    pub fn hash(&self) -> u64 {
+        let width = self.width.to_le_bytes();
+        let height = self.height.to_le_bytes();
+
+        rsh_wgpu::fnv1a(width.iter().chain(&height).chain(&self.data))
+    }
+}
+
+/// Renders a frame with `draw` into an offscreen target and reads it back
+///
+/// Swap chain images can't be copied from, so the frame is rendered again
+/// into a texture of the same `format` instead.
+pub fn capture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    format: OutputFormat,
+    width: u32,
+    height: u32,
+    draw: impl FnOnce(&mut wgpu::CommandEncoder, &wgpu::TextureView),
+) -> Frame {
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth: 1,
+    };
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(Cow::Borrowed("Capture Texture")),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: format.texture_format(),
+        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let row_size = width * bytes_per_pixel(format);
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_row_size = (row_size + align - 1) / align * align;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(Cow::Borrowed("Capture Buffer")),
+        size: (padded_row_size * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some(Cow::Borrowed("Capture Encoder")),
+    });
+
+    draw(&mut encoder, &view);
+
+    encoder.copy_texture_to_buffer(
+        wgpu::TextureCopyView {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        wgpu::BufferCopyView {
+            buffer: &buffer,
+            layout: wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: padded_row_size,
+                rows_per_image: height,
+            },
+        },
+        size,
+    );
+
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let mapping = slice.map_async(wgpu::MapMode::Read);
+    device.poll(wgpu::Maintain::Wait);
+    block_on(mapping).unwrap();
+
+    let mut data = Vec::with_capacity((row_size * height) as usize);
+    for row in slice.get_mapped_range().chunks(padded_row_size as usize) {
+        data.extend_from_slice(&row[..row_size as usize]);
+    }
+
+    Frame {
+        width,
+        height,
+        data: to_rgba8(format, data),
+    }
+}
+
+fn bytes_per_pixel(format: OutputFormat) -> u32 {
+    match format {
+        OutputFormat::Bgra8Srgb => 4,
+        OutputFormat::Rgba16Float => 8,
+    }
+}
+
+/// Converts pixels read back from a target of `format` to RGBA8
+fn to_rgba8(format: OutputFormat, mut data: Vec<u8>) -> Vec<u8> {
+    match format {
+        OutputFormat::Bgra8Srgb => {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+
+            data
+        }
+        // Float targets hold linear values, so encode them like an sRGB target would
+        OutputFormat::Rgba16Float => {
+            let mut rgba = Vec::with_capacity(data.len() / 2);
+
+            for pixel in data.chunks_exact(8) {
+                let channel = |i: usize| f16_to_f32(u16::from_le_bytes([pixel[i], pixel[i + 1]]));
+                let alpha = (channel(6).max(0.0).min(1.0) * 255.0).round() as u8;
+
+                rgba.extend_from_slice(&[
+                    linear_to_srgb(channel(0)),
+                    linear_to_srgb(channel(2)),
+                    linear_to_srgb(channel(4)),
+                    alpha,
+                ]);
+            }
+
+            rgba
+        }
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((half >> 10) & 0x1f);
+    let mantissa = f32::from(half & 0x3ff);
+
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.max(0.0).min(1.0);
+    let encoded = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (encoded * 255.0).round() as u8
+}
+
+/// Puts `frame` on the system clipboard
+pub fn copy_to_clipboard(frame: &Frame) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new()?.set_image(arboard::ImageData {
+        width: frame.width as usize,
+        height: frame.height as usize,
+        bytes: Cow::Borrowed(&frame.data),
+    })
+}
+
+/// Saves `frame` as a PNG image at `path`
+pub fn save_png(frame: &Frame, path: &Path) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        frame.width,
+        frame.height,
+    );
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&frame.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f16_special_values() {
+        let bits = |half: u16| f16_to_f32(half).to_bits();
+
+        assert_eq!(bits(0x3c00), 1f32.to_bits());
+        assert_eq!(bits(0xbc00), (-1f32).to_bits());
+        // Smallest subnormal
+        assert_eq!(bits(0x0001), 2f32.powi(-24).to_bits());
+        assert_eq!(bits(0x7c00), f32::INFINITY.to_bits());
+        assert!(f16_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn bgra8_to_rgba8() {
+        assert_eq!(
+            to_rgba8(OutputFormat::Bgra8Srgb, vec![1, 2, 3, 4]),
+            [3, 2, 1, 4]
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Deserialize;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+use winit::event_loop::EventLoopProxy;
+
+use crate::shader::Stage;
+
+/// Command accepted by the control socket, sent as one JSON object per line
+///
+/// ```json
+/// {"command": "set_clear_color", "color": "#1a334d"}
+/// {"command": "load_shader", "stage": "fragment", "path": "shader.rsh"}
+/// {"command": "set_shader_source", "stage": "fragment", "source": "..."}
+/// {"command": "capture_frame", "path": "frame.png"}
+/// ```
+///
+/// `set_clear_color` takes the same `#rrggbb` or `cycle` values as
+/// `--clear-color`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    SetClearColor { color: String },
+    LoadShader { stage: Stage, path: PathBuf },
+    SetShaderSource { stage: Stage, source: String },
+    CaptureFrame { path: PathBuf },
+}
+
+/// A command forwarded to the event loop, the result is sent back through
+/// `reply` and written to the connection as `{"ok": true}` or
+/// `{"ok": false, "error": "..."}`
+pub struct Request {
+    pub command: Command,
+    pub reply: mpsc::Sender<Result<(), String>>,
+}
+
+/// Listens for control connections on the unix socket at `path`
+#[cfg(unix)]
+pub fn spawn(path: &Path, proxy: EventLoopProxy<Request>) -> io::Result<()> {
+    use std::{
+        fs,
+        os::unix::{fs::FileTypeExt, net::UnixListener},
+        thread,
+    };
+
+    // A socket left behind by a previous run would make the bind fail, but
+    // anything else at `path` is most likely a mistyped argument
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and isn't a socket", path.display()),
+            ))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let listener = UnixListener::bind(path)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let proxy = proxy.clone();
+
+                    thread::spawn(move || {
+                        if let Err(e) = handle(stream, proxy) {
+                            eprintln!("Control connection failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => eprintln!("Failed to accept control connection: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn spawn(_: &Path, _: EventLoopProxy<Request>) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "The control socket is only supported on unix platforms",
+    ))
+}
+
+#[cfg(unix)]
+fn handle(
+    stream: std::os::unix::net::UnixStream,
+    proxy: EventLoopProxy<Request>,
+) -> io::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let response = match dispatch(&proxy, &line?, |_| true) {
+            Some(response) => response,
+            // The event loop has exited
+            None => break,
+        };
+
+        writeln!(writer, "{}", response)?;
+    }
+
+    Ok(())
+}
+
+/// Parses `message` as a [`Command`] and waits for the event loop to run
+/// it if `allowed`, returns `None` if the event loop has exited
+pub fn dispatch(
+    proxy: &EventLoopProxy<Request>,
+    message: &str,
+    allowed: impl Fn(&Command) -> bool,
+) -> Option<serde_json::Value> {
+    let result = match serde_json::from_str(message) {
+        Ok(command) if !allowed(&command) => {
+            Err(String::from("Command isn't allowed on this connection"))
+        }
+        Ok(command) => {
+            let (reply, receiver) = mpsc::channel();
+            proxy.send_event(Request { command, reply }).ok()?;
+
+            receiver
+                .recv()
+                .unwrap_or_else(|_| Err(String::from("Command was dropped")))
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    Some(match result {
+        Ok(()) => serde_json::json!({ "ok": true }),
+        Err(error) => serde_json::json!({ "ok": false, "error": error }),
+    })
+}
@@ -0,0 +1,95 @@
+/// Features of the pinned wgpu revision reported by name
+const FEATURES: &[(wgpu::Features, &str)] = &[
+    (
+        wgpu::Features::MAPPABLE_PRIMARY_BUFFERS,
+        "MAPPABLE_PRIMARY_BUFFERS",
+    ),
+    (
+        wgpu::Features::SAMPLED_TEXTURE_BINDING_ARRAY,
+        "SAMPLED_TEXTURE_BINDING_ARRAY",
+    ),
+    (
+        wgpu::Features::SAMPLED_TEXTURE_ARRAY_DYNAMIC_INDEXING,
+        "SAMPLED_TEXTURE_ARRAY_DYNAMIC_INDEXING",
+    ),
+    (
+        wgpu::Features::SAMPLED_TEXTURE_ARRAY_NON_UNIFORM_INDEXING,
+        "SAMPLED_TEXTURE_ARRAY_NON_UNIFORM_INDEXING",
+    ),
+    (
+        wgpu::Features::UNSIZED_BINDING_ARRAY,
+        "UNSIZED_BINDING_ARRAY",
+    ),
+    (wgpu::Features::MULTI_DRAW_INDIRECT, "MULTI_DRAW_INDIRECT"),
+    (
+        wgpu::Features::MULTI_DRAW_INDIRECT_COUNT,
+        "MULTI_DRAW_INDIRECT_COUNT",
+    ),
+    (wgpu::Features::PUSH_CONSTANTS, "PUSH_CONSTANTS"),
+];
+
+/// Prints the capabilities of every adapter, either as JSON or in a human
+/// readable form
+pub fn print(json: bool) {
+    let instance = wgpu::Instance::new(wgpu::BackendBit::all());
+    let adapters: Vec<_> = instance
+        .enumerate_adapters(wgpu::BackendBit::all())
+        .collect();
+
+    if json {
+        let adapters: Vec<_> = adapters.iter().map(adapter_json).collect();
+        println!("{}", serde_json::Value::Array(adapters));
+        return;
+    }
+
+    for adapter in adapters {
+        let info = adapter.get_info();
+
+        println!("{} ({:?}, {:?})", info.name, info.backend, info.device_type);
+        println!("  Features: {:?}", adapter.features());
+        println!("  Limits: {:?}", adapter.limits());
+    }
+}
+
+fn adapter_json(adapter: &wgpu::Adapter) -> serde_json::Value {
+    let info = adapter.get_info();
+    let limits = adapter.limits();
+    let features = adapter.features();
+    let mut feature_names: Vec<_> = FEATURES
+        .iter()
+        .filter(|(feature, _)| features.contains(*feature))
+        .map(|(_, name)| String::from(*name))
+        .collect();
+
+    // Flags added to wgpu after this list was written are still reported,
+    // just without a name
+    let unknown = FEATURES
+        .iter()
+        .fold(features, |features, (feature, _)| features - *feature);
+    if !unknown.is_empty() {
+        feature_names.push(format!("{:#x}", unknown.bits()));
+    }
+
+    serde_json::json!({
+        "name": info.name,
+        "vendor": info.vendor,
+        "device": info.device,
+        "device_type": format!("{:?}", info.device_type),
+        "backend": format!("{:?}", info.backend),
+        "features": feature_names,
+        "limits": {
+            "max_bind_groups": limits.max_bind_groups,
+            "max_dynamic_uniform_buffers_per_pipeline_layout":
+                limits.max_dynamic_uniform_buffers_per_pipeline_layout,
+            "max_dynamic_storage_buffers_per_pipeline_layout":
+                limits.max_dynamic_storage_buffers_per_pipeline_layout,
+            "max_sampled_textures_per_shader_stage": limits.max_sampled_textures_per_shader_stage,
+            "max_samplers_per_shader_stage": limits.max_samplers_per_shader_stage,
+            "max_storage_buffers_per_shader_stage": limits.max_storage_buffers_per_shader_stage,
+            "max_storage_textures_per_shader_stage": limits.max_storage_textures_per_shader_stage,
+            "max_uniform_buffers_per_shader_stage": limits.max_uniform_buffers_per_shader_stage,
+            "max_uniform_buffer_binding_size": limits.max_uniform_buffer_binding_size,
+            "max_push_constant_size": limits.max_push_constant_size,
+        },
+    })
+}
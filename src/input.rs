@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, LineWriter, Write},
+    path::Path,
+};
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+/// The subset of window events that can be recorded and replayed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputEvent {
+    Keyboard {
+        input: KeyboardInput,
+    },
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    MouseInput {
+        state: ElementState,
+        button: MouseButton,
+    },
+    Resized {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match *event {
+            WindowEvent::KeyboardInput { input, .. } => Some(InputEvent::Keyboard { input }),
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::CursorMoved {
+                x: position.x,
+                y: position.y,
+            }),
+            WindowEvent::MouseInput { state, button, .. } => {
+                Some(InputEvent::MouseInput { state, button })
+            }
+            WindowEvent::Resized(size) => Some(InputEvent::Resized {
+                width: size.width,
+                height: size.height,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The key this event presses, if any
+    pub fn pressed_key(&self) -> Option<VirtualKeyCode> {
+        match *self {
+            InputEvent::Keyboard {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode,
+                        ..
+                    },
+            } => virtual_keycode,
+            _ => None,
+        }
+    }
+}
+
+/// An input event and the time in seconds since startup it happened at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Recorded {
+    time: f32,
+    event: InputEvent,
+}
+
+/// Writes input events to a file as one JSON object per line
+pub struct Recorder {
+    writer: LineWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Recorder {
+            writer: LineWriter::new(File::create(path)?),
+        })
+    }
+
+    /// Appends `event`, every line is flushed right away so the recording
+    /// survives a crash
+    pub fn record(&mut self, time: f32, event: &InputEvent) -> io::Result<()> {
+        let recorded = Recorded {
+            time,
+            event: event.clone(),
+        };
+
+        serde_json::to_writer(&mut self.writer, &recorded)?;
+        writeln!(self.writer)
+    }
+}
+
+/// Input events loaded from a recording, handed out as time advances
+pub struct Replay {
+    events: Vec<Recorded>,
+    next: usize,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let events = BufReader::new(File::open(path)?)
+            .lines()
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Replay { events, next: 0 })
+    }
+
+    /// Time of the last recorded event
+    pub fn duration(&self) -> f32 {
+        self.events.last().map_or(0.0, |recorded| recorded.time)
+    }
+
+    /// Starts handing out events from the beginning of the recording again
+    pub fn rewind(&mut self) {
+        self.next = 0;
+    }
+
+    /// Events recorded up to `time` that weren't handed out yet
+    pub fn due(&mut self, time: f32) -> Vec<InputEvent> {
+        let start = self.next;
+
+        while self.next < self.events.len() && self.events[self.next].time <= time {
+            self.next += 1;
+        }
+
+        self.events[start..self.next]
+            .iter()
+            .map(|recorded| recorded.event.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A replay of resizes at `times`, each one `i` pixels wide
+    fn replay(times: &[f32]) -> Replay {
+        let events = times
+            .iter()
+            .enumerate()
+            .map(|(i, &time)| Recorded {
+                time,
+                event: InputEvent::Resized {
+                    width: i as u32,
+                    height: 1,
+                },
+            })
+            .collect();
+
+        Replay { events, next: 0 }
+    }
+
+    fn widths(events: Vec<InputEvent>) -> Vec<u32> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                InputEvent::Resized { width, .. } => width,
+                event => panic!("Unexpected event {:?}", event),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn events_are_due_at_their_time() {
+        let mut replay = replay(&[0.0, 1.0, 1.0, 2.0]);
+
+        assert_eq!(widths(replay.due(0.0)), [0]);
+        assert!(replay.due(0.5).is_empty());
+        assert_eq!(widths(replay.due(1.0)), [1, 2]);
+        assert!(replay.due(1.0).is_empty());
+    }
+
+    #[test]
+    fn rewind_hands_out_events_again() {
+        let mut replay = replay(&[0.0, 1.0]);
+
+        assert_eq!(widths(replay.due(1.0)), [0, 1]);
+        replay.rewind();
+        assert_eq!(widths(replay.due(0.0)), [0]);
+    }
+
+    #[test]
+    fn seeking_past_the_end_hands_out_the_rest() {
+        let mut replay = replay(&[0.0, 1.0, 2.0]);
+
+        assert_eq!(widths(replay.due(0.0)), [0]);
+        let past_end = replay.duration() + 10.0;
+        assert_eq!(widths(replay.due(past_end)), [1, 2]);
+        assert!(replay.due(f32::INFINITY).is_empty());
+    }
+}
@@ -0,0 +1,40 @@
+//! Rendering and shader loading shared by the demo, the shader corpus runner
+//! and the fuzz targets
+use std::any::Any;
+
+pub mod arena;
+pub mod renderer;
+pub mod shader;
+
+/// Message of a panic caught with `catch_unwind`, wgpu reports validation
+/// errors by panicking at this revision
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|e| e.to_string()))
+        .unwrap_or_else(|| String::from("Unknown panic"))
+}
+
+/// FNV-1a hash of `bytes`, stable across platforms and builds unlike the
+/// standard library's hashers, so it can key files and compare runs
+pub fn fnv1a<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.into_iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
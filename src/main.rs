@@ -1,56 +1,35 @@
 use futures::executor::block_on;
-use wgpu::util::DeviceExt;
+use std::panic::{self, AssertUnwindSafe};
 use winit::{
     event::*,
     event_loop::{ControlFlow, EventLoop},
 };
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug)]
-struct Vertex {
-    position: [f32; 4],
-    color: [f32; 4],
-}
-
-unsafe impl bytemuck::Pod for Vertex {}
-unsafe impl bytemuck::Zeroable for Vertex {}
-
-impl Vertex {
-    fn desc<'a>() -> wgpu::VertexBufferDescriptor<'a> {
-        use std::mem;
-        wgpu::VertexBufferDescriptor {
-            stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::InputStepMode::Vertex,
-            attributes: std::borrow::Cow::Borrowed(&[
-                wgpu::VertexAttributeDescriptor {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float4,
-                },
-                wgpu::VertexAttributeDescriptor {
-                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float4,
-                },
-            ]),
-        }
-    }
-}
-
-const VERTICES: &[Vertex] = &[
-    Vertex {
-        position: [0.0, 0.5, 0.0, 1.0],
-        color: [1.0, 0.0, 0.0, 1.0],
-    },
-    Vertex {
-        position: [-0.5, -0.5, 0.0, 1.0],
-        color: [0.0, 1.0, 0.0, 1.0],
-    },
-    Vertex {
-        position: [0.5, -0.5, 0.0, 1.0],
-        color: [0.0, 0.0, 1.0, 1.0],
-    },
-];
+mod app;
+mod capture;
+mod control;
+mod info;
+mod input;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod options;
+mod pipe;
+mod stats;
+#[cfg(feature = "websocket")]
+mod websocket;
+
+use rsh_wgpu::{renderer, shader};
+
+use app::App;
+use control::Request;
+use input::InputEvent;
+use options::Options;
+use renderer::Renderer;
+use shader::Stage;
+
+/// Window size used when hashing frames, hashes are only comparable
+/// between frames of the same size
+const HASH_SIZE: (u32, u32) = (640, 480);
 
 const VERT_SHADER: &str = r#"
 global in=0 v_position: Vector<4, Float>;
@@ -83,8 +62,31 @@ fn fragment main() {
 "#;
 
 fn main() {
-    let event_loop = EventLoop::new();
-    let window = winit::window::Window::new(&event_loop).unwrap();
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if options.info {
+        info::print(options.json);
+        return;
+    }
+
+    let event_loop = EventLoop::<Request>::with_user_event();
+    let mut window_builder = winit::window::WindowBuilder::new();
+    if options.hash_frames.is_some() {
+        window_builder = window_builder
+            .with_inner_size(winit::dpi::PhysicalSize::new(HASH_SIZE.0, HASH_SIZE.1))
+            .with_resizable(false);
+    }
+    if options.pipe_frames.is_some() {
+        // The stream's size is fixed by its header
+        window_builder = window_builder.with_resizable(false);
+    }
+    let window = window_builder.build(&event_loop).unwrap();
 
     let size = window.inner_size();
 
@@ -108,132 +110,115 @@ fn main() {
     ))
     .unwrap();
 
-    let mut sc_desc = wgpu::SwapChainDescriptor {
+    let sc_desc = wgpu::SwapChainDescriptor {
         usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-        format: wgpu::TextureFormat::Bgra8UnormSrgb,
+        format: options.format.texture_format(),
         width: size.width,
         height: size.height,
         present_mode: wgpu::PresentMode::Mailbox,
     };
-    let mut swap_chain = device.create_swap_chain(&surface, &sc_desc);
-
-    let vs_spirv = rusty_shades::compile_to_spirv(VERT_SHADER).unwrap();
-    let vs_module = device.create_shader_module(wgpu::ShaderModuleSource::SpirV(
-        std::borrow::Cow::from(vs_spirv),
-    ));
-    let fs_spirv = rusty_shades::compile_to_spirv(FRAG_SHADER).unwrap();
-    let fs_module = device.create_shader_module(wgpu::ShaderModuleSource::SpirV(
-        std::borrow::Cow::from(fs_spirv),
-    ));
-
-    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        bind_group_layouts: std::borrow::Cow::Borrowed(&[]),
-        push_constant_ranges: std::borrow::Cow::Borrowed(&[]),
-    });
 
-    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Vertex Buffer"),
-        contents: bytemuck::cast_slice(&VERTICES),
-        usage: wgpu::BufferUsage::VERTEX,
-    });
+    if let Err(e) = check_swap_chain(&device, &surface, &sc_desc) {
+        eprintln!(
+            "The surface doesn't support the `{:?}` format: {}",
+            sc_desc.format, e
+        );
+        std::process::exit(1);
+    }
 
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        layout: &render_pipeline_layout,
-        vertex_stage: wgpu::ProgrammableStageDescriptor {
-            module: &vs_module,
-            entry_point: std::borrow::Cow::Borrowed("main"),
-        },
-        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-            module: &fs_module,
-            entry_point: std::borrow::Cow::Borrowed("main"),
-        }),
-        rasterization_state: None,
-        color_states: std::borrow::Cow::Borrowed(&[wgpu::ColorStateDescriptor {
-            format: sc_desc.format,
-            color_blend: wgpu::BlendDescriptor::REPLACE,
-            alpha_blend: wgpu::BlendDescriptor::REPLACE,
-            write_mask: wgpu::ColorWrite::ALL,
-        }]),
-        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
-        depth_stencil_state: None,
-        vertex_state: wgpu::VertexStateDescriptor {
-            index_format: wgpu::IndexFormat::Uint16,
-            vertex_buffers: std::borrow::Cow::Borrowed(&[Vertex::desc()]),
-        },
-        sample_count: 1,
-        sample_mask: !0,
-        alpha_to_coverage_enabled: false,
-    });
+    let shader_cache = options
+        .shader_cache
+        .as_ref()
+        .map(|dir| shader::Cache::open(dir).unwrap());
+
+    let vs_spirv = match options.vert_spv {
+        Some(ref path) => shader::load_spv(path).unwrap(),
+        None => shader::compile_cached(VERT_SHADER, shader_cache.as_ref()).unwrap(),
+    };
+    let fs_spirv = match options.frag_spv {
+        Some(ref path) => shader::load_spv(path).unwrap(),
+        None => shader::compile_cached(FRAG_SHADER, shader_cache.as_ref()).unwrap(),
+    };
+
+    if let Some(ref dir) = options.emit_spv {
+        shader::emit_spv(dir, Stage::Vertex, &vs_spirv).unwrap();
+        shader::emit_spv(dir, Stage::Fragment, &fs_spirv).unwrap();
+    }
+
+    let mut renderer = Renderer::new(
+        &device,
+        sc_desc.format,
+        sc_desc.width,
+        sc_desc.height,
+        &vs_spirv,
+        &fs_spirv,
+    );
+    renderer.outline = options.outline;
+
+    if let Some(ref path) = options.control {
+        control::spawn(path, event_loop.create_proxy()).unwrap();
+    }
+
+    #[cfg(feature = "websocket")]
+    {
+        if let Some(ref addr) = options.websocket {
+            let origins = options.websocket_origins.clone();
+            websocket::spawn(addr, origins, event_loop.create_proxy()).unwrap();
+        }
+    }
+
+    let mut app = App::new(
+        window,
+        surface,
+        device,
+        queue,
+        sc_desc,
+        renderer,
+        shader_cache,
+        options,
+    );
 
     event_loop.run(move |event, _, control_flow| {
-        let _ = (
-            &instance,
-            &adapter,
-            &vs_module,
-            &fs_module,
-            &render_pipeline_layout,
-        );
+        let _ = (&instance, &adapter);
 
         *control_flow = ControlFlow::Poll;
         match event {
             Event::WindowEvent {
                 ref event,
                 window_id,
-            } if window_id == window.id() => match event {
+            } if window_id == app.window.id() => match event {
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                WindowEvent::KeyboardInput { input, .. } => match input {
-                    KeyboardInput {
-                        state: ElementState::Pressed,
-                        virtual_keycode: Some(VirtualKeyCode::Escape),
-                        ..
-                    } => *control_flow = ControlFlow::Exit,
-                    _ => {}
-                },
-                WindowEvent::Resized(size) => {
-                    sc_desc.width = size.width;
-                    sc_desc.height = size.height;
-                    swap_chain = device.create_swap_chain(&surface, &sc_desc);
+                event => {
+                    if let Some(input) = InputEvent::from_window_event(event) {
+                        app.window_input(input, control_flow);
+                    }
                 }
-                _ => {}
             },
-            Event::RedrawRequested(_) => {
-                let frame = swap_chain
-                    .get_current_frame()
-                    .expect("Timeout getting texture")
-                    .output;
-
-                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some(std::borrow::Cow::Borrowed("Render Encoder")),
-                });
-
-                {
-                    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                        color_attachments: std::borrow::Cow::Borrowed(&[
-                            wgpu::RenderPassColorAttachmentDescriptor {
-                                attachment: &frame.view,
-                                resolve_target: None,
-                                ops: wgpu::Operations {
-                                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                                        r: 0.1,
-                                        g: 0.2,
-                                        b: 0.3,
-                                        a: 1.0,
-                                    }),
-                                    store: true,
-                                },
-                            },
-                        ]),
-                        depth_stencil_attachment: None,
-                    });
-
-                    render_pass.set_pipeline(&render_pipeline);
-                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                    render_pass.draw(0..3, 0..1);
-                }
-
-                queue.submit(Some(encoder.finish()));
+            Event::MainEventsCleared => app.update(control_flow),
+            Event::RedrawRequested(_) => app.redraw(control_flow),
+            Event::UserEvent(Request { command, reply }) => {
+                let _ = reply.send(app.command(command));
             }
             _ => {}
         }
     });
 }
+
+/// Creates a swap chain for `sc_desc` once to see if the surface supports it
+///
+/// wgpu panics on unsupported formats, the panic is caught so it can be
+/// reported without a backtrace.
+fn check_swap_chain(
+    device: &wgpu::Device,
+    surface: &wgpu::Surface,
+    sc_desc: &wgpu::SwapChainDescriptor,
+) -> Result<(), String> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        device.create_swap_chain(surface, sc_desc);
+    }));
+    panic::set_hook(hook);
+
+    result.map_err(|payload| rsh_wgpu::panic_message(&*payload))
+}
@@ -0,0 +1,99 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How long a client has to send its request line
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Gauges updated by the event loop and exposed in the Prometheus text format
+#[derive(Debug, Default)]
+pub struct Metrics {
+    frames: AtomicU64,
+    /// Bits of the last frame time in seconds as a `f64`
+    frame_time: AtomicU64,
+}
+
+impl Metrics {
+    /// Counts a frame, `frame_time` is `None` for the first one
+    pub fn record_frame(&self, frame_time: Option<Duration>) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(frame_time) = frame_time {
+            self.frame_time
+                .store(frame_time.as_secs_f64().to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    fn render(&self) -> String {
+        let frames = self.frames.load(Ordering::Relaxed);
+        let frame_time = f64::from_bits(self.frame_time.load(Ordering::Relaxed));
+        let fps = if frame_time > 0.0 {
+            1.0 / frame_time
+        } else {
+            0.0
+        };
+
+        format!(
+            "# HELP rsh_wgpu_frames_total Frames rendered since startup.\n\
+             # TYPE rsh_wgpu_frames_total counter\n\
+             rsh_wgpu_frames_total {}\n\
+             # HELP rsh_wgpu_frame_time_seconds Time between the last two frames.\n\
+             # TYPE rsh_wgpu_frame_time_seconds gauge\n\
+             rsh_wgpu_frame_time_seconds {}\n\
+             # HELP rsh_wgpu_fps Frames per second derived from the last frame time.\n\
+             # TYPE rsh_wgpu_fps gauge\n\
+             rsh_wgpu_fps {}\n",
+            frames, frame_time, fps
+        )
+    }
+}
+
+/// Serves `metrics` over HTTP on `addr` at `/metrics`
+pub fn spawn(addr: &str, metrics: Arc<Metrics>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| handle(stream, &metrics));
+
+            if let Err(e) = result {
+                eprintln!("Failed to serve metrics: {}", e);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle(stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    // Connections are served one at a time, so an idle client mustn't
+    // block the others
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", metrics.render())
+    } else {
+        ("404 Not Found", String::new())
+    };
+
+    write!(
+        &stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+use crate::renderer::{ClearColor, OutputFormat};
+
+/// Options passed on the command line
+#[derive(Debug, Default)]
+pub struct Options {
+    /// Print the adapters' capabilities instead of running the demo
+    pub info: bool,
+    /// Print the adapters' capabilities as JSON
+    pub json: bool,
+    /// Directory where the compiled SPIR-V modules are written to
+    pub emit_spv: Option<PathBuf>,
+    /// Pre-compiled vertex module used instead of the rsh source
+    pub vert_spv: Option<PathBuf>,
+    /// Pre-compiled fragment module used instead of the rsh source
+    pub frag_spv: Option<PathBuf>,
+    /// Directory compiled rsh shaders are cached in
+    pub shader_cache: Option<PathBuf>,
+    /// Format of the swap chain
+    pub format: OutputFormat,
+    /// Color the target is cleared to, the renderer's default if `None`
+    pub clear_color: Option<ClearColor>,
+    /// Outline the triangle using the stencil buffer
+    pub outline: bool,
+    /// Animate the viewport between different aspect ratios
+    pub letterbox: bool,
+    /// Number of frames to render at a fixed timestep, printing a hash of
+    /// each one, before exiting
+    pub hash_frames: Option<u64>,
+    /// File or named pipe rendered frames are streamed to as Y4M, `-` for
+    /// stdout
+    pub pipe_frames: Option<PathBuf>,
+    /// Rate of the frame stream in frames per second
+    pub pipe_fps: u32,
+    /// File per-frame statistics are written to
+    pub stats_log: Option<PathBuf>,
+    /// Address the Prometheus metrics endpoint listens on
+    #[cfg(feature = "metrics")]
+    pub metrics: Option<String>,
+    /// File window input is recorded to
+    pub record: Option<PathBuf>,
+    /// Recording whose input is replayed instead of the window's
+    pub replay: Option<PathBuf>,
+    /// Path of the unix socket accepting control commands
+    pub control: Option<PathBuf>,
+    /// Address the live-coding WebSocket server listens on
+    #[cfg(feature = "websocket")]
+    pub websocket: Option<String>,
+    /// Browser origins allowed to connect to the WebSocket server
+    #[cfg(feature = "websocket")]
+    pub websocket_origins: Vec<String>,
+}
+
+impl Options {
+    pub fn from_args() -> Result<Self, String> {
+        let mut options = Options {
+            pipe_fps: 30,
+            ..Options::default()
+        };
+        let mut args = std::env::args().skip(1).peekable();
+
+        // `info` is a subcommand, so it's only recognized in front of the flags
+        if args.peek().map(String::as_str) == Some("info") {
+            args.next();
+            options.info = true;
+        }
+
+        // Arguments take precedence over the environment
+        if let Ok(color) = std::env::var("RSH_WGPU_CLEAR_COLOR") {
+            options.clear_color = Some(
+                ClearColor::parse(&color).map_err(|e| format!("RSH_WGPU_CLEAR_COLOR: {}", e))?,
+            );
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" if options.info => options.json = true,
+                "--json" => return Err(String::from("`--json` is only valid after `info`")),
+                "--emit-spv" => options.emit_spv = Some(value(&mut args, &arg)?.into()),
+                "--vert-spv" => options.vert_spv = Some(value(&mut args, &arg)?.into()),
+                "--frag-spv" => options.frag_spv = Some(value(&mut args, &arg)?.into()),
+                "--shader-cache" => options.shader_cache = Some(value(&mut args, &arg)?.into()),
+                "--format" => options.format = OutputFormat::parse(&value(&mut args, &arg)?)?,
+                "--clear-color" => {
+                    options.clear_color = Some(ClearColor::parse(&value(&mut args, &arg)?)?)
+                }
+                "--outline" => options.outline = true,
+                "--letterbox" => options.letterbox = true,
+                "--hash-frames" => {
+                    let count = value(&mut args, &arg)?;
+                    options.hash_frames = match count.parse() {
+                        Ok(count) if count > 0 => Some(count),
+                        _ => return Err(format!("Invalid frame count `{}`", count)),
+                    };
+                }
+                "--pipe-frames" => options.pipe_frames = Some(value(&mut args, &arg)?.into()),
+                "--pipe-fps" => {
+                    let fps = value(&mut args, &arg)?;
+                    options.pipe_fps = match fps.parse() {
+                        Ok(fps) if fps > 0 => fps,
+                        _ => return Err(format!("Invalid frame rate `{}`", fps)),
+                    };
+                }
+                "--stats-log" => options.stats_log = Some(value(&mut args, &arg)?.into()),
+                #[cfg(feature = "metrics")]
+                "--metrics" => options.metrics = Some(value(&mut args, &arg)?),
+                "--record" => options.record = Some(value(&mut args, &arg)?.into()),
+                "--replay" => options.replay = Some(value(&mut args, &arg)?.into()),
+                "--control" => options.control = Some(value(&mut args, &arg)?.into()),
+                #[cfg(feature = "websocket")]
+                "--websocket" => options.websocket = Some(value(&mut args, &arg)?),
+                #[cfg(feature = "websocket")]
+                "--websocket-origin" => options.websocket_origins.push(value(&mut args, &arg)?),
+                _ => return Err(format!("Unknown argument `{}`", arg)),
+            }
+        }
+
+        // Frame hashes are printed to stdout, where they would end up in the
+        // middle of the stream
+        let piping_to_stdout = options
+            .pipe_frames
+            .as_ref()
+            .map_or(false, |path| path.as_os_str() == "-");
+        if piping_to_stdout && options.hash_frames.is_some() {
+            return Err(String::from(
+                "`--hash-frames` can't be used while piping frames to stdout",
+            ));
+        }
+
+        Ok(options)
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for `{}`", flag))
+}
@@ -0,0 +1,130 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::capture::Frame;
+
+/// Writes frames as an uncompressed Y4M stream, which encoders such as
+/// ffmpeg read directly from a pipe
+///
+/// ```sh
+/// rsh-wgpu --pipe-frames - | ffmpeg -i - triangle.mp4
+/// ```
+pub struct FramePipe {
+    writer: BufWriter<Box<dyn Write>>,
+    width: u32,
+    height: u32,
+    fps: u32,
+    written: u64,
+}
+
+impl FramePipe {
+    /// Streams to `path`, which can be a named pipe, or to stdout if it's `-`
+    pub fn create(path: &Path, width: u32, height: u32, fps: u32) -> io::Result<Self> {
+        let writer: Box<dyn Write> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(File::create(path)?)
+        };
+        let mut writer = BufWriter::new(writer);
+
+        // 4:4:4 keeps the chroma at full resolution, so odd sizes need no
+        // special handling
+        writeln!(
+            writer,
+            "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+            width, height, fps
+        )?;
+
+        Ok(FramePipe {
+            writer,
+            width,
+            height,
+            fps,
+            written: 0,
+        })
+    }
+
+    /// Number of frames that should've been written by `time` seconds but
+    /// weren't yet
+    pub fn due(&self, time: f32) -> u64 {
+        let total = (f64::from(time) * f64::from(self.fps)).floor() as u64 + 1;
+        total.saturating_sub(self.written)
+    }
+
+    pub fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        if (frame.width, frame.height) != (self.width, self.height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Frame is {}x{} but the stream is {}x{}",
+                    frame.width, frame.height, self.width, self.height
+                ),
+            ));
+        }
+
+        let pixels = frame.width as usize * frame.height as usize;
+        let mut planes = vec![0; pixels * 3];
+
+        for (i, pixel) in frame.data.chunks_exact(4).enumerate() {
+            let (y, cb, cr) = to_ycbcr(pixel[0], pixel[1], pixel[2]);
+
+            planes[i] = y;
+            planes[pixels + i] = cb;
+            planes[pixels * 2 + i] = cr;
+        }
+
+        self.writer.write_all(b"FRAME\n")?;
+        self.writer.write_all(&planes)?;
+        self.writer.flush()?;
+        self.written += 1;
+
+        Ok(())
+    }
+}
+
+/// Converts to limited range BT.601, which is what Y4M readers assume
+fn to_ycbcr(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let (r, g, b) = (f32::from(r), f32::from(g), f32::from(b));
+
+    let y = 16.0 + 0.256_788 * r + 0.504_129 * g + 0.097_906 * b;
+    let cb = 128.0 - 0.148_223 * r - 0.290_993 * g + 0.439_216 * b;
+    let cr = 128.0 + 0.439_216 * r - 0.367_788 * g - 0.071_427 * b;
+
+    (y.round() as u8, cb.round() as u8, cr.round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipe(fps: u32, written: u64) -> FramePipe {
+        FramePipe {
+            writer: BufWriter::new(Box::new(io::sink())),
+            width: 1,
+            height: 1,
+            fps,
+            written,
+        }
+    }
+
+    #[test]
+    fn first_frame_is_due_at_start() {
+        assert_eq!(pipe(30, 0).due(0.0), 1);
+        assert_eq!(pipe(30, 1).due(0.0), 0);
+    }
+
+    #[test]
+    fn frames_are_due_when_behind() {
+        assert_eq!(pipe(30, 1).due(0.1), 3);
+        assert_eq!(pipe(30, 10).due(0.1), 0);
+    }
+
+    #[test]
+    fn black_is_limited_range() {
+        assert_eq!(to_ycbcr(0, 0, 0), (16, 128, 128));
+        assert_eq!(to_ycbcr(255, 255, 255), (235, 128, 128));
+    }
+}
@@ -0,0 +1,725 @@
+use std::{
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{
+    arena::{Arena, ArenaBuilder},
+    panic_message,
+    shader::{self, Stage},
+};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct Vertex {
+    position: [f32; 4],
+    color: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for Vertex {}
+unsafe impl bytemuck::Zeroable for Vertex {}
+
+impl Vertex {
+    fn desc<'a>() -> wgpu::VertexBufferDescriptor<'a> {
+        use std::mem;
+        wgpu::VertexBufferDescriptor {
+            stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: std::borrow::Cow::Borrowed(&[
+                wgpu::VertexAttributeDescriptor {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float4,
+                },
+                wgpu::VertexAttributeDescriptor {
+                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float4,
+                },
+            ]),
+        }
+    }
+}
+
+const VERTICES: &[Vertex] = &[
+    Vertex {
+        position: [0.0, 0.5, 0.0, 1.0],
+        color: [1.0, 0.0, 0.0, 1.0],
+    },
+    Vertex {
+        position: [-0.5, -0.5, 0.0, 1.0],
+        color: [0.0, 1.0, 0.0, 1.0],
+    },
+    Vertex {
+        position: [0.5, -0.5, 0.0, 1.0],
+        color: [0.0, 0.0, 1.0, 1.0],
+    },
+];
+
+const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+/// Scale of the outline relative to the triangle
+const OUTLINE_SCALE: f32 = 1.15;
+const OUTLINE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+const BAR_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// Two triangles covering the whole viewport, scissored to draw the bars
+/// around the scissor rect
+fn bar_vertices() -> Vec<Vertex> {
+    [
+        [-1.0, -1.0],
+        [1.0, -1.0],
+        [1.0, 1.0],
+        [-1.0, -1.0],
+        [1.0, 1.0],
+        [-1.0, 1.0],
+    ]
+    .iter()
+    .map(|&[x, y]| Vertex {
+        position: [x, y, 0.0, 1.0],
+        color: BAR_COLOR,
+    })
+    .collect()
+}
+
+/// Builds the outline by scaling the triangle around its centroid
+fn outline_vertices() -> Vec<Vertex> {
+    let count = VERTICES.len() as f32;
+    let center_x = VERTICES.iter().map(|v| v.position[0]).sum::<f32>() / count;
+    let center_y = VERTICES.iter().map(|v| v.position[1]).sum::<f32>() / count;
+
+    VERTICES
+        .iter()
+        .map(|v| Vertex {
+            position: [
+                center_x + (v.position[0] - center_x) * OUTLINE_SCALE,
+                center_y + (v.position[1] - center_y) * OUTLINE_SCALE,
+                v.position[2],
+                v.position[3],
+            ],
+            color: OUTLINE_COLOR,
+        })
+        .collect()
+}
+
+/// Region of the render target the scene is drawn to, in pixels
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    /// Largest viewport with the given `aspect` ratio centered in a `width`
+    /// by `height` target, leaving bars on the sides that don't fit
+    pub fn letterbox(width: u32, height: u32, aspect: f32) -> Self {
+        let (width, height) = (width as f32, height as f32);
+        let (inner_width, inner_height) = if width / height > aspect {
+            (height * aspect, height)
+        } else {
+            (width, width / aspect)
+        };
+
+        Viewport {
+            x: ((width - inner_width) / 2.0).floor(),
+            y: ((height - inner_height) / 2.0).floor(),
+            width: inner_width.floor(),
+            height: inner_height.floor(),
+        }
+    }
+}
+
+/// Region of the render target outside of which fragments are discarded
+#[derive(Debug, Clone, Copy)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScissorRect {
+    /// The non-empty parts of a `width` by `height` target outside of the
+    /// rect, as full width bars above and below it and bars on its sides
+    pub fn surrounding(self, width: u32, height: u32) -> Vec<ScissorRect> {
+        let bottom = self.y + self.height;
+        let right = self.x + self.width;
+
+        vec![
+            ScissorRect {
+                x: 0,
+                y: 0,
+                width,
+                height: self.y,
+            },
+            ScissorRect {
+                x: 0,
+                y: bottom,
+                width,
+                height: height - bottom,
+            },
+            ScissorRect {
+                x: 0,
+                y: self.y,
+                width: self.x,
+                height: self.height,
+            },
+            ScissorRect {
+                x: right,
+                y: self.y,
+                width: width - right,
+                height: self.height,
+            },
+        ]
+        .into_iter()
+        .filter(|bar| bar.width > 0 && bar.height > 0)
+        .collect()
+    }
+}
+
+impl From<Viewport> for ScissorRect {
+    fn from(viewport: Viewport) -> Self {
+        ScissorRect {
+            x: viewport.x as u32,
+            y: viewport.y as u32,
+            width: viewport.width as u32,
+            height: viewport.height as u32,
+        }
+    }
+}
+
+/// Formats the swap chain can be created with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Bgra8Srgb,
+    Rgba16Float,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Bgra8Srgb
+    }
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "bgra8-srgb" => Ok(OutputFormat::Bgra8Srgb),
+            "rgba16f" => Ok(OutputFormat::Rgba16Float),
+            format => Err(format!(
+                "Invalid format `{}`, expected bgra8-srgb or rgba16f",
+                format
+            )),
+        }
+    }
+
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            OutputFormat::Bgra8Srgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+            OutputFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+}
+
+/// Seconds [`ClearColor::Cycle`] takes to go through every hue
+const HUE_CYCLE_PERIOD: f32 = 12.0;
+
+/// Color the render target is cleared to
+#[derive(Debug, Clone, Copy)]
+pub enum ClearColor {
+    /// An sRGB color like `#1a334d`
+    Srgb([u8; 3]),
+    /// Cycles through every hue over time
+    Cycle,
+}
+
+impl ClearColor {
+    /// Parses either `cycle` or a `#rrggbb` color
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if value == "cycle" {
+            return Ok(ClearColor::Cycle);
+        }
+
+        let invalid = || format!("Invalid clear color `{}`, expected #rrggbb or cycle", value);
+        let hex = value.strip_prefix('#').ok_or_else(invalid)?;
+        // `from_str_radix` would also accept signs
+        if hex.len() != 6 || !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+
+        Ok(ClearColor::Srgb([channel(0), channel(2), channel(4)]))
+    }
+
+    /// The color at `time` seconds as a linear clear value
+    pub fn at(self, time: f32) -> wgpu::Color {
+        let [r, g, b] = match self {
+            ClearColor::Srgb([r, g, b]) => [
+                f32::from(r) / 255.0,
+                f32::from(g) / 255.0,
+                f32::from(b) / 255.0,
+            ],
+            ClearColor::Cycle => hue((time / HUE_CYCLE_PERIOD).rem_euclid(1.0)),
+        };
+
+        // sRGB targets encode the clear value and float targets hold linear
+        // values, so both take it linear
+        wgpu::Color {
+            r: srgb_to_linear(r),
+            g: srgb_to_linear(g),
+            b: srgb_to_linear(b),
+            a: 1.0,
+        }
+    }
+}
+
+/// A muted sRGB color of `hue`, which goes from 0 to 1 around the color wheel
+fn hue(hue: f32) -> [f32; 3] {
+    const SATURATION: f32 = 0.6;
+    const VALUE: f32 = 0.8;
+
+    let sector = hue * 6.0;
+    let x = 1.0 - (sector % 2.0 - 1.0).abs();
+    let [r, g, b] = match sector as u32 {
+        0 => [1.0, x, 0.0],
+        1 => [x, 1.0, 0.0],
+        2 => [0.0, 1.0, x],
+        3 => [0.0, x, 1.0],
+        4 => [x, 0.0, 1.0],
+        _ => [1.0, 0.0, x],
+    };
+
+    let chroma = VALUE * SATURATION;
+    let min = VALUE - chroma;
+    [min + chroma * r, min + chroma * g, min + chroma * b]
+}
+
+fn srgb_to_linear(value: f32) -> f64 {
+    let value = f64::from(value);
+
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub struct Renderer {
+    format: wgpu::TextureFormat,
+    vs_module: wgpu::ShaderModule,
+    fs_module: wgpu::ShaderModule,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    outline_pipeline: wgpu::RenderPipeline,
+    /// Holds the vertices of the triangle, its outline and the bars
+    arena: Arena,
+    triangle: Range<u32>,
+    outline_triangle: Range<u32>,
+    bar_quad: Range<u32>,
+    depth_stencil_view: wgpu::TextureView,
+    width: u32,
+    height: u32,
+    viewport: Option<Viewport>,
+    scissor_rect: Option<ScissorRect>,
+    pub clear_color: wgpu::Color,
+    /// Whether the triangle is outlined using the stencil buffer
+    pub outline: bool,
+}
+
+impl Renderer {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        vs_spirv: &[u32],
+        fs_spirv: &[u32],
+    ) -> Self {
+        let vs_module = create_shader_module(device, vs_spirv);
+        let fs_module = create_shader_module(device, fs_spirv);
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                bind_group_layouts: std::borrow::Cow::Borrowed(&[]),
+                push_constant_ranges: std::borrow::Cow::Borrowed(&[]),
+            });
+
+        let mut arena = ArenaBuilder::default();
+        let triangle = arena.push_vertices(VERTICES);
+        let outline_triangle = arena.push_vertices(&outline_vertices());
+        let bar_quad = arena.push_vertices(&bar_vertices());
+        let arena = arena.build(device, "Vertex Arena");
+
+        let (render_pipeline, outline_pipeline) = create_render_pipelines(
+            device,
+            &render_pipeline_layout,
+            &vs_module,
+            &fs_module,
+            format,
+        );
+
+        Renderer {
+            format,
+            vs_module,
+            fs_module,
+            render_pipeline_layout,
+            render_pipeline,
+            outline_pipeline,
+            arena,
+            triangle,
+            outline_triangle,
+            bar_quad,
+            depth_stencil_view: create_depth_stencil_view(device, width, height),
+            width,
+            height,
+            viewport: None,
+            scissor_rect: None,
+            clear_color: wgpu::Color {
+                r: 0.1,
+                g: 0.2,
+                b: 0.3,
+                a: 1.0,
+            },
+            outline: false,
+        }
+    }
+
+    /// Recreates the size dependent attachments, must be called whenever
+    /// the render target changes size
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.depth_stencil_view = create_depth_stencil_view(device, width, height);
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Restricts drawing to `viewport`, `None` uses the whole target
+    pub fn set_viewport(&mut self, viewport: Option<Viewport>) {
+        self.viewport = viewport;
+    }
+
+    /// Discards fragments outside of `rect` and covers the rest of the
+    /// target with black bars, `None` disables the test. The rect must lie
+    /// within the render target.
+    pub fn set_scissor_rect(&mut self, rect: Option<ScissorRect>) {
+        self.scissor_rect = rect;
+    }
+
+    /// Replaces the module used for `stage` and rebuilds the pipelines, the
+    /// current ones are kept if the new module fails validation
+    pub fn set_shader(
+        &mut self,
+        device: &wgpu::Device,
+        stage: Stage,
+        spirv: &[u32],
+    ) -> Result<(), String> {
+        // A module of the wrong stage fails validation too, but this gives
+        // a clearer error for files loaded into the wrong stage
+        if let Some(found) = shader::entry_stage(spirv).filter(|&found| found != stage) {
+            return Err(format!(
+                "Expected a {:?} shader but the module's entry point is a {:?} shader",
+                stage, found
+            ));
+        }
+
+        // wgpu reports validation errors by panicking at this revision, so
+        // a module that compiled can still fail here, for example when its
+        // interface doesn't match the other stage
+        let (module, (render_pipeline, outline_pipeline)) =
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                let module = create_shader_module(device, spirv);
+                let (vs_module, fs_module) = match stage {
+                    Stage::Vertex => (&module, &self.fs_module),
+                    Stage::Fragment => (&self.vs_module, &module),
+                };
+                let pipelines = create_render_pipelines(
+                    device,
+                    &self.render_pipeline_layout,
+                    vs_module,
+                    fs_module,
+                    self.format,
+                );
+
+                (module, pipelines)
+            }))
+            .map_err(|payload| panic_message(&*payload))?;
+
+        match stage {
+            Stage::Vertex => self.vs_module = module,
+            Stage::Fragment => self.fs_module = module,
+        }
+        self.render_pipeline = render_pipeline;
+        self.outline_pipeline = outline_pipeline;
+
+        Ok(())
+    }
+
+    /// Number of draw calls recorded by `draw`
+    pub fn draw_calls(&self) -> u32 {
+        let bars = self.bars().len() as u32;
+
+        if self.outline {
+            bars + 2
+        } else {
+            bars + 1
+        }
+    }
+
+    fn bars(&self) -> Vec<ScissorRect> {
+        self.scissor_rect
+            .map_or_else(Vec::new, |rect| rect.surrounding(self.width, self.height))
+    }
+
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: std::borrow::Cow::Borrowed(&[
+                wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        store: true,
+                    },
+                },
+            ]),
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                attachment: &self.depth_stencil_view,
+                depth_ops: None,
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: false,
+                }),
+            }),
+        });
+
+        // The triangle marks its pixels in the stencil buffer so the
+        // enlarged outline only covers the pixels around it
+        render_pass.set_stencil_reference(1);
+
+        // The meshes share the arena and its stride, so it's bound once
+        render_pass.set_vertex_buffer(0, self.arena.slice());
+        render_pass.set_pipeline(&self.render_pipeline);
+
+        // Clearing ignores the scissor rect, so the bars are drawn over the
+        // cleared target with a scissor rect of their own
+        let bars = self.bars();
+        if !bars.is_empty() {
+            render_pass.set_viewport(0.0, 0.0, self.width as f32, self.height as f32, 0.0, 1.0);
+
+            for bar in bars {
+                render_pass.set_scissor_rect(bar.x, bar.y, bar.width, bar.height);
+                render_pass.draw(self.bar_quad.clone(), 0..1);
+            }
+        }
+
+        if let Some(viewport) = self.viewport {
+            render_pass.set_viewport(
+                viewport.x,
+                viewport.y,
+                viewport.width,
+                viewport.height,
+                0.0,
+                1.0,
+            );
+        }
+        if let Some(rect) = self.scissor_rect {
+            render_pass.set_scissor_rect(rect.x, rect.y, rect.width, rect.height);
+        }
+
+        render_pass.draw(self.triangle.clone(), 0..1);
+
+        if self.outline {
+            render_pass.set_pipeline(&self.outline_pipeline);
+            render_pass.draw(self.outline_triangle.clone(), 0..1);
+        }
+    }
+}
+
+fn create_shader_module(device: &wgpu::Device, spirv: &[u32]) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleSource::SpirV(std::borrow::Cow::Borrowed(
+        spirv,
+    )))
+}
+
+fn create_depth_stencil_view(device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some(std::borrow::Cow::Borrowed("Depth Stencil Texture")),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_STENCIL_FORMAT,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// Creates the triangle pipeline, which writes to the stencil buffer, and
+/// the outline pipeline, which only draws where the stencil wasn't written
+fn create_render_pipelines(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    vs_module: &wgpu::ShaderModule,
+    fs_module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+    let stencil_write = wgpu::StencilStateFaceDescriptor {
+        compare: wgpu::CompareFunction::Always,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op: wgpu::StencilOperation::Replace,
+    };
+    let stencil_test = wgpu::StencilStateFaceDescriptor {
+        compare: wgpu::CompareFunction::NotEqual,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op: wgpu::StencilOperation::Keep,
+    };
+
+    (
+        create_render_pipeline(device, layout, vs_module, fs_module, format, stencil_write),
+        create_render_pipeline(device, layout, vs_module, fs_module, format, stencil_test),
+    )
+}
+
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    vs_module: &wgpu::ShaderModule,
+    fs_module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    stencil: wgpu::StencilStateFaceDescriptor,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        layout,
+        vertex_stage: wgpu::ProgrammableStageDescriptor {
+            module: vs_module,
+            entry_point: std::borrow::Cow::Borrowed("main"),
+        },
+        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+            module: fs_module,
+            entry_point: std::borrow::Cow::Borrowed("main"),
+        }),
+        rasterization_state: None,
+        color_states: std::borrow::Cow::Borrowed(&[wgpu::ColorStateDescriptor {
+            format,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        }]),
+        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+        depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
+            format: DEPTH_STENCIL_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilStateDescriptor {
+                front: stencil.clone(),
+                back: stencil,
+                read_mask: !0,
+                write_mask: !0,
+            },
+        }),
+        vertex_state: wgpu::VertexStateDescriptor {
+            index_format: wgpu::IndexFormat::Uint16,
+            vertex_buffers: std::borrow::Cow::Borrowed(&[Vertex::desc()]),
+        },
+        sample_count: 1,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Option<[u8; 3]> {
+        match ClearColor::parse(value) {
+            Ok(ClearColor::Srgb(color)) => Some(color),
+            Ok(ClearColor::Cycle) => panic!("`{}` parsed as cycle", value),
+            Err(_) => None,
+        }
+    }
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert!(
+                (actual - expected).abs() < 1e-5,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn parse_clear_color() {
+        assert_eq!(parse("#1a334d"), Some([0x1a, 0x33, 0x4d]));
+        assert_eq!(parse("#1A334D"), Some([0x1a, 0x33, 0x4d]));
+        assert!(matches!(ClearColor::parse("cycle"), Ok(ClearColor::Cycle)));
+    }
+
+    #[test]
+    fn parse_invalid_clear_color() {
+        assert_eq!(parse("1a334d"), None);
+        assert_eq!(parse("#fff"), None);
+        assert_eq!(parse("#1a334d00"), None);
+        assert_eq!(parse("#+f+f+f"), None);
+        assert_eq!(parse("#1a334g"), None);
+    }
+
+    #[test]
+    fn clear_color_is_linearized() {
+        let grey = ClearColor::Srgb([0x80, 0x80, 0x80]).at(0.0);
+
+        assert!((grey.r - 0.2158).abs() < 1e-4);
+        assert!((grey.a - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn hue_sector_boundaries() {
+        assert_close(hue(0.0), [0.8, 0.32, 0.32]);
+        assert_close(hue(1.0 / 6.0), [0.8, 0.8, 0.32]);
+        assert_close(hue(0.5), [0.32, 0.8, 0.8]);
+        assert_close(hue(5.0 / 6.0), [0.8, 0.32, 0.8]);
+    }
+
+    fn bars(rect: [u32; 4], width: u32, height: u32) -> Vec<[u32; 4]> {
+        let [x, y, w, h] = rect;
+        ScissorRect {
+            x,
+            y,
+            width: w,
+            height: h,
+        }
+        .surrounding(width, height)
+        .into_iter()
+        .map(|bar| [bar.x, bar.y, bar.width, bar.height])
+        .collect()
+    }
+
+    #[test]
+    fn letterbox_bars() {
+        assert_eq!(
+            bars([0, 10, 100, 30], 100, 50),
+            [[0, 0, 100, 10], [0, 40, 100, 10]]
+        );
+        assert_eq!(
+            bars([20, 0, 60, 50], 100, 50),
+            [[0, 0, 20, 50], [80, 0, 20, 50]]
+        );
+    }
+
+    #[test]
+    fn no_bars_around_the_whole_target() {
+        assert!(bars([0, 0, 100, 50], 100, 50).is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+use serde::Deserialize;
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// SPIR-V magic number, used to detect the module's endianness
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Vertex,
+    Fragment,
+}
+
+impl Stage {
+    /// Tag used in the file names of the stage's artifacts
+    pub fn tag(self) -> &'static str {
+        match self {
+            Stage::Vertex => "vert",
+            Stage::Fragment => "frag",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Compile(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Compile(e) => write!(f, "Failed to compile shader: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Compiles rsh `source` to SPIR-V
+pub fn compile(source: &str) -> Result<Vec<u32>, Error> {
+    rusty_shades::compile_to_spirv(source).map_err(|e| Error::Compile(format!("{:?}", e)))
+}
+
+/// Compiles rsh `source` through `cache` if there is one
+pub fn compile_cached(source: &str, cache: Option<&Cache>) -> Result<Vec<u32>, Error> {
+    match cache {
+        Some(cache) => cache.compile(source),
+        None => compile(source),
+    }
+}
+
+/// Loads the shader at `path`, either a pre-compiled `.spv` module or an rsh
+/// source which gets compiled
+pub fn load(path: &Path, cache: Option<&Cache>) -> Result<Vec<u32>, Error> {
+    if path.extension().map_or(false, |ext| ext == "spv") {
+        Ok(load_spv(path)?)
+    } else {
+        compile_cached(&fs::read_to_string(path)?, cache)
+    }
+}
+
+/// Stage of the first entry point declared by `spirv`
+pub fn entry_stage(spirv: &[u32]) -> Option<Stage> {
+    const OP_ENTRY_POINT: u32 = 15;
+    const EXECUTION_MODEL_VERTEX: u32 = 0;
+    const EXECUTION_MODEL_FRAGMENT: u32 = 4;
+
+    // Instructions start after the 5 word header, each one's first word
+    // holds its length in words and its opcode
+    let mut offset = 5;
+    while let Some(&word) = spirv.get(offset) {
+        let length = (word >> 16) as usize;
+
+        if word & 0xffff == OP_ENTRY_POINT {
+            return match spirv.get(offset + 1) {
+                Some(&EXECUTION_MODEL_VERTEX) => Some(Stage::Vertex),
+                Some(&EXECUTION_MODEL_FRAGMENT) => Some(Stage::Fragment),
+                _ => None,
+            };
+        }
+
+        if length == 0 {
+            return None;
+        }
+        offset += length;
+    }
+
+    None
+}
+
+/// Compiled modules kept on disk under the hash of their source and the
+/// compiler, so unchanged shaders aren't compiled again on later launches
+pub struct Cache {
+    dir: PathBuf,
+    compiler: u64,
+}
+
+impl Cache {
+    /// Uses `dir` as the cache, creating it if needed
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        Ok(Cache {
+            dir: dir.to_path_buf(),
+            compiler: compiler_id()?,
+        })
+    }
+
+    pub fn compile(&self, source: &str) -> Result<Vec<u32>, Error> {
+        let compiler = self.compiler.to_le_bytes();
+        let hash = crate::fnv1a(compiler.iter().chain(source.as_bytes()));
+        let path = self.dir.join(format!("{:016x}.spv", hash));
+
+        if let Ok(spirv) = load_spv(&path) {
+            return Ok(spirv);
+        }
+
+        let spirv = compile(source)?;
+
+        // Failing to store the module only costs compiling it again next time
+        if let Err(e) = store(&path, &spirv) {
+            eprintln!("Failed to cache {}: {}", path.display(), e);
+        }
+
+        Ok(spirv)
+    }
+}
+
+/// Writes `spirv` to `path` through a temporary file, so a crash or another
+/// instance can't leave a truncated module behind for later launches
+fn store(path: &Path, spirv: &[u32]) -> io::Result<()> {
+    let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+
+    fs::write(&temp, bytemuck::cast_slice::<u32, u8>(spirv))?;
+    fs::rename(&temp, path).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        e
+    })
+}
+
+/// Identifies the build of rusty_shades modules are compiled with
+///
+/// It's a path dependency whose version doesn't change with its code, so
+/// the size and modification time of the executable it's linked into stand
+/// in for it and every rebuild starts with fresh entries.
+fn compiler_id() -> io::Result<u64> {
+    let metadata = fs::metadata(std::env::current_exe()?)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut id = metadata.len().to_le_bytes().to_vec();
+    id.extend_from_slice(&modified.as_nanos().to_le_bytes());
+
+    Ok(crate::fnv1a(&id))
+}
+
+/// Writes `spirv` to `dir` as `triangle.<stage>.spv`, creating `dir` if needed
+pub fn emit_spv(dir: &Path, stage: Stage, spirv: &[u32]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(format!("triangle.{}.spv", stage.tag())),
+        bytemuck::cast_slice::<u32, u8>(spirv),
+    )
+}
+
+/// Reads a pre-compiled SPIR-V module from `path`
+pub fn load_spv(path: &Path) -> io::Result<Vec<u32>> {
+    parse_spv(&fs::read(path)?)
+}
+
+/// Converts the bytes of a SPIR-V module to words in native endianness
+pub fn parse_spv(bytes: &[u8]) -> io::Result<Vec<u32>> {
+    if bytes.len() % 4 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "SPIR-V module size isn't a multiple of 4",
+        ));
+    }
+
+    let words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+
+    match words.first() {
+        Some(&SPIRV_MAGIC) => Ok(words),
+        Some(magic) if magic.swap_bytes() == SPIRV_MAGIC => {
+            Ok(words.into_iter().map(u32::swap_bytes).collect())
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing SPIR-V magic number",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_little_endian_spv() {
+        let bytes: Vec<u8> = [SPIRV_MAGIC, 1]
+            .iter()
+            .flat_map(|word| word.to_le_bytes().to_vec())
+            .collect();
+
+        assert_eq!(parse_spv(&bytes).unwrap(), [SPIRV_MAGIC, 1]);
+    }
+
+    #[test]
+    fn parse_byte_swapped_spv() {
+        let bytes: Vec<u8> = [SPIRV_MAGIC, 1]
+            .iter()
+            .flat_map(|word| word.to_be_bytes().to_vec())
+            .collect();
+
+        assert_eq!(parse_spv(&bytes).unwrap(), [SPIRV_MAGIC, 1]);
+    }
+
+    #[test]
+    fn reject_truncated_spv() {
+        let mut bytes = SPIRV_MAGIC.to_le_bytes().to_vec();
+        bytes.push(0);
+
+        let error = parse_spv(&bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn entry_point_stage() {
+        // OpEntryPoint Fragment %main "main" after an OpCapability Shader
+        let module = [
+            SPIRV_MAGIC,
+            0x0001_0000,
+            0,
+            1,
+            0,
+            0x0002_0011,
+            1,
+            0x0004_000f,
+            4,
+            1,
+            0,
+        ];
+        assert_eq!(entry_stage(&module), Some(Stage::Fragment));
+
+        let mut module = module;
+        module[8] = 0;
+        assert_eq!(entry_stage(&module), Some(Stage::Vertex));
+
+        assert_eq!(entry_stage(&module[..7]), None);
+    }
+
+    #[test]
+    fn reject_missing_magic() {
+        assert!(parse_spv(&[0; 8]).is_err());
+        assert!(parse_spv(&[]).is_err());
+    }
+}
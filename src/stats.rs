@@ -0,0 +1,37 @@
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{self, LineWriter, Write},
+    path::Path,
+};
+
+/// Statistics of a single rendered frame
+#[derive(Debug, Serialize)]
+pub struct FrameStats {
+    pub frame: u64,
+    /// Animation time the frame was rendered at, in seconds
+    pub time: f32,
+    /// Time since the previous frame started, in milliseconds
+    pub frame_time_ms: Option<f64>,
+    /// Time spent encoding and submitting the frame, in milliseconds
+    pub cpu_time_ms: f64,
+    pub draw_calls: u32,
+}
+
+/// Writes frame statistics to a file as one JSON object per line
+pub struct StatsLog {
+    writer: LineWriter<File>,
+}
+
+impl StatsLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(StatsLog {
+            writer: LineWriter::new(File::create(path)?),
+        })
+    }
+
+    pub fn write(&mut self, stats: &FrameStats) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, stats)?;
+        writeln!(self.writer)
+    }
+}
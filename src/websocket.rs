@@ -0,0 +1,92 @@
+use std::{
+    io,
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+};
+use tungstenite::{
+    handshake::server::{ErrorResponse, Request as Handshake, Response},
+    http::StatusCode,
+    Message,
+};
+use winit::event_loop::EventLoopProxy;
+
+use crate::control::{self, Command, Request};
+
+/// Listens for WebSocket connections on `addr`
+///
+/// Text messages are handled as control commands, limited to
+/// `set_shader_source` and `set_clear_color`, so editors can push rsh
+/// sources and get compile and validation errors back. Browsers let any
+/// page connect to local servers, so handshakes carrying an `Origin`
+/// header are only accepted for the `origins` given, and `addr` should be a
+/// loopback address like `127.0.0.1:9001`.
+pub fn spawn(addr: &str, origins: Vec<String>, proxy: EventLoopProxy<Request>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let origins = Arc::new(origins);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let proxy = proxy.clone();
+                    let origins = origins.clone();
+                    thread::spawn(move || handle(stream, &origins, proxy));
+                }
+                Err(e) => eprintln!("Failed to accept WebSocket connection: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle(stream: TcpStream, origins: &[String], proxy: EventLoopProxy<Request>) {
+    let check_origin = |request: &Handshake, response: Response| {
+        // Clients other than browsers don't send an origin
+        let allowed = match request.headers().get("Origin") {
+            Some(origin) => origins
+                .iter()
+                .any(|allowed| allowed.as_bytes() == origin.as_bytes()),
+            None => true,
+        };
+
+        if allowed {
+            Ok(response)
+        } else {
+            let mut response = ErrorResponse::new(Some(String::from("Origin not allowed")));
+            *response.status_mut() = StatusCode::FORBIDDEN;
+            Err(response)
+        }
+    };
+
+    let mut socket = match tungstenite::accept_hdr(stream, check_origin) {
+        Ok(socket) => socket,
+        Err(e) => return eprintln!("WebSocket handshake failed: {}", e),
+    };
+
+    loop {
+        let message = match socket.read_message() {
+            Ok(Message::Text(message)) => message,
+            Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => break,
+            Ok(_) => continue,
+            Err(e) => return eprintln!("WebSocket connection failed: {}", e),
+        };
+
+        let allowed = |command: &Command| {
+            matches!(
+                command,
+                Command::SetShaderSource { .. } | Command::SetClearColor { .. }
+            )
+        };
+        let response = match control::dispatch(&proxy, &message, allowed) {
+            Some(response) => response,
+            // The event loop has exited
+            None => break,
+        };
+
+        if let Err(e) = socket.write_message(Message::Text(response.to_string())) {
+            return eprintln!("WebSocket connection failed: {}", e);
+        }
+    }
+}